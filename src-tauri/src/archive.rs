@@ -0,0 +1,437 @@
+//! Minimal ZIP reader used to pull MAPINFO-family lumps out of PK3/PK7 archives.
+//!
+//! PK3/PK7 files are ordinary ZIP containers, so rather than pull in a full
+//! archive crate we walk the handful of structures we actually need:
+//! the End-of-Central-Directory record, the central directory entries, and
+//! the local file header that precedes each entry's data.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+
+const EOCD_SIGNATURE: u32 = 0x0605_4b50;
+const CENTRAL_DIR_SIGNATURE: u32 = 0x0201_4b50;
+const LOCAL_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+
+/// A file named in the ZIP central directory.
+struct CentralDirEntry {
+    name: String,
+    compressed_size: u32,
+    compression_method: u16,
+    local_header_offset: u32,
+}
+
+/// Quick check for the ZIP local-file-header signature at the start of a file.
+pub fn is_zip(header: &[u8]) -> bool {
+    header.len() >= 4 && header[0..4] == [b'P', b'K', 0x03, 0x04]
+}
+
+/// Read the named entries out of a ZIP/PK3/PK7 archive, matching by base name
+/// (case-insensitive, ignoring any subdirectory prefix).
+pub fn read_named_entries(
+    file: &mut File,
+    wanted_names: &[&str],
+) -> Result<HashMap<String, String>, String> {
+    let entries = read_central_directory(file)?;
+    let mut found = HashMap::new();
+
+    for entry in &entries {
+        let base_name = entry
+            .name
+            .rsplit(['/', '\\'])
+            .next()
+            .unwrap_or(&entry.name)
+            .to_uppercase();
+
+        let Some(wanted) = wanted_names
+            .iter()
+            .find(|w| w.eq_ignore_ascii_case(&base_name))
+        else {
+            continue;
+        };
+
+        let data = read_entry_data(file, entry)?;
+        found.insert(wanted.to_uppercase(), data);
+    }
+
+    Ok(found)
+}
+
+fn read_central_directory(file: &mut File) -> Result<Vec<CentralDirEntry>, String> {
+    let eocd_offset = find_eocd(file)?;
+
+    file.seek(SeekFrom::Start(eocd_offset))
+        .map_err(|e| format!("Failed to seek to EOCD: {}", e))?;
+
+    let mut eocd = [0u8; 22];
+    file.read_exact(&mut eocd)
+        .map_err(|e| format!("Failed to read EOCD: {}", e))?;
+
+    let entry_count = u16::from_le_bytes([eocd[10], eocd[11]]) as u32;
+    let central_dir_offset = u32::from_le_bytes([eocd[16], eocd[17], eocd[18], eocd[19]]);
+
+    file.seek(SeekFrom::Start(central_dir_offset as u64))
+        .map_err(|e| format!("Failed to seek to central directory: {}", e))?;
+
+    let mut entries = Vec::with_capacity(entry_count as usize);
+    for _ in 0..entry_count {
+        let mut fixed = [0u8; 46];
+        file.read_exact(&mut fixed)
+            .map_err(|e| format!("Failed to read central directory entry: {}", e))?;
+
+        let signature = u32::from_le_bytes([fixed[0], fixed[1], fixed[2], fixed[3]]);
+        if signature != CENTRAL_DIR_SIGNATURE {
+            return Err(format!(
+                "Invalid central directory entry signature: {:#010x}",
+                signature
+            ));
+        }
+
+        let compression_method = u16::from_le_bytes([fixed[10], fixed[11]]);
+        let compressed_size = u32::from_le_bytes([fixed[20], fixed[21], fixed[22], fixed[23]]);
+        let name_len = u16::from_le_bytes([fixed[28], fixed[29]]) as usize;
+        let extra_len = u16::from_le_bytes([fixed[30], fixed[31]]) as usize;
+        let comment_len = u16::from_le_bytes([fixed[32], fixed[33]]) as usize;
+        let local_header_offset = u32::from_le_bytes([fixed[42], fixed[43], fixed[44], fixed[45]]);
+
+        let mut name_buf = vec![0u8; name_len];
+        file.read_exact(&mut name_buf)
+            .map_err(|e| format!("Failed to read entry name: {}", e))?;
+        let name = String::from_utf8_lossy(&name_buf).to_string();
+
+        file.seek(SeekFrom::Current((extra_len + comment_len) as i64))
+            .map_err(|e| format!("Failed to skip central directory extras: {}", e))?;
+
+        entries.push(CentralDirEntry {
+            name,
+            compressed_size,
+            compression_method,
+            local_header_offset,
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Scan backward from EOF for the EOCD signature. The record is at least 22
+/// bytes and may be followed by up to 64KiB of archive comment, so search the
+/// trailing 64KiB + header window.
+fn find_eocd(file: &mut File) -> Result<u64, String> {
+    let file_len = file
+        .metadata()
+        .map_err(|e| format!("Failed to stat archive: {}", e))?
+        .len();
+
+    const EOCD_MIN_SIZE: u64 = 22;
+    const MAX_COMMENT_SIZE: u64 = 65535;
+    let search_window = (EOCD_MIN_SIZE + MAX_COMMENT_SIZE).min(file_len);
+    let search_start = file_len - search_window;
+
+    let mut buf = vec![0u8; search_window as usize];
+    file.seek(SeekFrom::Start(search_start))
+        .map_err(|e| format!("Failed to seek while searching for EOCD: {}", e))?;
+    file.read_exact(&mut buf)
+        .map_err(|e| format!("Failed to read archive tail: {}", e))?;
+
+    for i in (0..=buf.len().saturating_sub(4)).rev() {
+        let sig = u32::from_le_bytes([buf[i], buf[i + 1], buf[i + 2], buf[i + 3]]);
+        if sig == EOCD_SIGNATURE {
+            return Ok(search_start + i as u64);
+        }
+    }
+
+    Err("Not a valid ZIP archive: End-of-Central-Directory record not found".to_string())
+}
+
+fn read_entry_data(file: &mut File, entry: &CentralDirEntry) -> Result<String, String> {
+    file.seek(SeekFrom::Start(entry.local_header_offset as u64))
+        .map_err(|e| format!("Failed to seek to local header: {}", e))?;
+
+    let mut fixed = [0u8; 30];
+    file.read_exact(&mut fixed)
+        .map_err(|e| format!("Failed to read local header: {}", e))?;
+
+    let signature = u32::from_le_bytes([fixed[0], fixed[1], fixed[2], fixed[3]]);
+    if signature != LOCAL_HEADER_SIGNATURE {
+        return Err(format!(
+            "Invalid local file header signature: {:#010x}",
+            signature
+        ));
+    }
+
+    let name_len = u16::from_le_bytes([fixed[26], fixed[27]]) as i64;
+    let extra_len = u16::from_le_bytes([fixed[28], fixed[29]]) as i64;
+    file.seek(SeekFrom::Current(name_len + extra_len))
+        .map_err(|e| format!("Failed to skip local header name/extra fields: {}", e))?;
+
+    let mut compressed = vec![0u8; entry.compressed_size as usize];
+    file.read_exact(&mut compressed)
+        .map_err(|e| format!("Failed to read entry data for '{}': {}", entry.name, e))?;
+
+    let raw = match entry.compression_method {
+        0 => compressed,
+        8 => inflate(&compressed)
+            .map_err(|e| format!("Failed to inflate entry '{}': {}", entry.name, e))?,
+        other => {
+            return Err(format!(
+                "Unsupported compression method {} for entry '{}'",
+                other, entry.name
+            ))
+        }
+    };
+
+    Ok(String::from_utf8_lossy(&raw).to_string())
+}
+
+/// A bit reader over a byte slice, LSB-first as used by DEFLATE (RFC 1951).
+struct BitReader<'a> {
+    data: &'a [u8],
+    byte_pos: usize,
+    bit_pos: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        BitReader {
+            data,
+            byte_pos: 0,
+            bit_pos: 0,
+        }
+    }
+
+    fn read_bit(&mut self) -> Result<u32, String> {
+        let byte = *self
+            .data
+            .get(self.byte_pos)
+            .ok_or("Unexpected end of DEFLATE stream")?;
+        let bit = (byte >> self.bit_pos) & 1;
+        self.bit_pos += 1;
+        if self.bit_pos == 8 {
+            self.bit_pos = 0;
+            self.byte_pos += 1;
+        }
+        Ok(bit as u32)
+    }
+
+    fn read_bits(&mut self, count: u32) -> Result<u32, String> {
+        let mut value = 0u32;
+        for i in 0..count {
+            value |= self.read_bit()? << i;
+        }
+        Ok(value)
+    }
+
+    fn align_to_byte(&mut self) {
+        if self.bit_pos != 0 {
+            self.bit_pos = 0;
+            self.byte_pos += 1;
+        }
+    }
+}
+
+/// A canonical Huffman decoder built from per-symbol code lengths.
+struct HuffmanTree {
+    // (code length, code value) -> symbol, looked up by walking bit-by-bit.
+    symbols_by_length: Vec<Vec<(u32, u16)>>,
+}
+
+impl HuffmanTree {
+    fn from_lengths(lengths: &[u8]) -> HuffmanTree {
+        let max_len = lengths.iter().copied().max().unwrap_or(0) as usize;
+        let mut bl_count = vec![0u32; max_len + 1];
+        for &len in lengths {
+            if len > 0 {
+                bl_count[len as usize] += 1;
+            }
+        }
+
+        let mut code = 0u32;
+        let mut next_code = vec![0u32; max_len + 1];
+        for bits in 1..=max_len {
+            code = (code + bl_count[bits - 1]) << 1;
+            next_code[bits] = code;
+        }
+
+        let mut symbols_by_length = vec![Vec::new(); max_len + 1];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len == 0 {
+                continue;
+            }
+            let len = len as usize;
+            let assigned = next_code[len];
+            next_code[len] += 1;
+            symbols_by_length[len].push((assigned, symbol as u16));
+        }
+
+        HuffmanTree { symbols_by_length }
+    }
+
+    fn decode(&self, bits: &mut BitReader) -> Result<u16, String> {
+        let mut code = 0u32;
+        for len in 1..self.symbols_by_length.len() {
+            code = (code << 1) | bits.read_bit()?;
+            for &(assigned, symbol) in &self.symbols_by_length[len] {
+                if assigned == code {
+                    return Ok(symbol);
+                }
+            }
+        }
+        Err("Invalid Huffman code in DEFLATE stream".to_string())
+    }
+}
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA_BITS: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA_BITS: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// Decompress a raw DEFLATE (RFC 1951) stream, as used by ZIP compression
+/// method 8.
+fn inflate(data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut bits = BitReader::new(data);
+    let mut out = Vec::new();
+
+    loop {
+        let is_final = bits.read_bit()? == 1;
+        let block_type = bits.read_bits(2)?;
+
+        match block_type {
+            0 => inflate_stored_block(&mut bits, &mut out)?,
+            1 => {
+                let (lit_tree, dist_tree) = fixed_huffman_trees();
+                inflate_huffman_block(&mut bits, &lit_tree, &dist_tree, &mut out)?;
+            }
+            2 => {
+                let (lit_tree, dist_tree) = read_dynamic_huffman_trees(&mut bits)?;
+                inflate_huffman_block(&mut bits, &lit_tree, &dist_tree, &mut out)?;
+            }
+            _ => return Err("Invalid DEFLATE block type".to_string()),
+        }
+
+        if is_final {
+            break;
+        }
+    }
+
+    Ok(out)
+}
+
+fn inflate_stored_block(bits: &mut BitReader, out: &mut Vec<u8>) -> Result<(), String> {
+    bits.align_to_byte();
+    let len_lo = *bits.data.get(bits.byte_pos).ok_or("Truncated stored block")? as u32;
+    let len_hi = *bits.data.get(bits.byte_pos + 1).ok_or("Truncated stored block")? as u32;
+    let len = len_lo | (len_hi << 8);
+    bits.byte_pos += 4; // skip LEN and ~LEN
+
+    let slice = bits
+        .data
+        .get(bits.byte_pos..bits.byte_pos + len as usize)
+        .ok_or("Truncated stored block data")?;
+    out.extend_from_slice(slice);
+    bits.byte_pos += len as usize;
+    Ok(())
+}
+
+fn fixed_huffman_trees() -> (HuffmanTree, HuffmanTree) {
+    let mut lit_lengths = [0u8; 288];
+    for (i, len) in lit_lengths.iter_mut().enumerate() {
+        *len = match i {
+            0..=143 => 8,
+            144..=255 => 9,
+            256..=279 => 7,
+            _ => 8,
+        };
+    }
+    let dist_lengths = [5u8; 30];
+
+    (
+        HuffmanTree::from_lengths(&lit_lengths),
+        HuffmanTree::from_lengths(&dist_lengths),
+    )
+}
+
+fn read_dynamic_huffman_trees(bits: &mut BitReader) -> Result<(HuffmanTree, HuffmanTree), String> {
+    let hlit = bits.read_bits(5)? as usize + 257;
+    let hdist = bits.read_bits(5)? as usize + 1;
+    let hclen = bits.read_bits(4)? as usize + 4;
+
+    let mut code_length_lengths = [0u8; 19];
+    for &order in CODE_LENGTH_ORDER.iter().take(hclen) {
+        code_length_lengths[order] = bits.read_bits(3)? as u8;
+    }
+    let code_length_tree = HuffmanTree::from_lengths(&code_length_lengths);
+
+    let mut lengths = Vec::with_capacity(hlit + hdist);
+    while lengths.len() < hlit + hdist {
+        let symbol = code_length_tree.decode(bits)?;
+        match symbol {
+            0..=15 => lengths.push(symbol as u8),
+            16 => {
+                let repeat = bits.read_bits(2)? + 3;
+                let prev = *lengths.last().ok_or("Invalid length repeat code")?;
+                lengths.extend(std::iter::repeat_n(prev, repeat as usize));
+            }
+            17 => {
+                let repeat = bits.read_bits(3)? + 3;
+                lengths.extend(std::iter::repeat_n(0u8, repeat as usize));
+            }
+            18 => {
+                let repeat = bits.read_bits(7)? + 11;
+                lengths.extend(std::iter::repeat_n(0u8, repeat as usize));
+            }
+            _ => return Err("Invalid code length symbol".to_string()),
+        }
+    }
+
+    let lit_tree = HuffmanTree::from_lengths(&lengths[..hlit]);
+    let dist_tree = HuffmanTree::from_lengths(&lengths[hlit..hlit + hdist]);
+    Ok((lit_tree, dist_tree))
+}
+
+fn inflate_huffman_block(
+    bits: &mut BitReader,
+    lit_tree: &HuffmanTree,
+    dist_tree: &HuffmanTree,
+    out: &mut Vec<u8>,
+) -> Result<(), String> {
+    loop {
+        let symbol = lit_tree.decode(bits)?;
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            257..=285 => {
+                let idx = (symbol - 257) as usize;
+                let extra = bits.read_bits(LENGTH_EXTRA_BITS[idx] as u32)?;
+                let length = LENGTH_BASE[idx] as u32 + extra;
+
+                let dist_symbol = dist_tree.decode(bits)? as usize;
+                let dist_extra = bits.read_bits(DIST_EXTRA_BITS[dist_symbol] as u32)?;
+                let distance = DIST_BASE[dist_symbol] as u32 + dist_extra;
+
+                let start = out
+                    .len()
+                    .checked_sub(distance as usize)
+                    .ok_or("Invalid back-reference distance in DEFLATE stream")?;
+                for i in 0..length as usize {
+                    let byte = out[start + i];
+                    out.push(byte);
+                }
+            }
+            _ => return Err("Invalid literal/length symbol".to_string()),
+        }
+    }
+}
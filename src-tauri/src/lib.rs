@@ -1,31 +1,45 @@
 use std::collections::HashMap;
 use std::path::Path;
 use std::process::Command;
+use tauri::AppHandle;
+use tauri_plugin_shell::ShellExt;
 
+mod archive;
+mod process;
 mod wad_parser;
 
-/// Check if a process with the given name is running.
+/// Check if a process with the given name is running, on Linux, macOS, or
+/// Windows.
 #[tauri::command]
 async fn is_process_running(process_name: String) -> Result<bool, String> {
-    let output = Command::new("pgrep")
-        .arg("-x")
-        .arg(&process_name)
-        .output()
-        .map_err(|e| format!("Failed to run pgrep: {}", e))?;
-
-    Ok(output.status.success())
+    process::is_process_running(&process_name)
 }
 
 /// Extract level names from a WAD file's MAPINFO/ZMAPINFO/UMAPINFO/DEHACKED lumps.
 /// Returns a map of level ID (e.g., "MAP01") to level name (e.g., "Entryway").
-/// Only includes levels that have names defined in the WAD.
+/// Levels with no name defined anywhere in the WAD are still included, keyed
+/// to themselves (e.g. "MAP01" -> "MAP01"), as long as the lump directory
+/// shows the level exists.
 #[tauri::command]
 async fn extract_wad_level_names(wad_path: String) -> Result<HashMap<String, String>, String> {
     wad_parser::extract_level_names(&wad_path)
 }
 
-/// Extract level names and save them to a JSON file alongside the WAD.
-/// Creates a file named "{wad_filename}.levels.json" in the same directory.
+/// Extract structured per-level metadata (name, music, next/secret-next map,
+/// par time, cluster) from a WAD file's MAPINFO/ZMAPINFO/UMAPINFO/DEHACKED
+/// lumps, in discovery order; `episode` is derived from classic `ExMy` level
+/// IDs. A level with no defined name uses its ID as the name if the WAD's
+/// lump directory shows it as a map; other unnamed levels (including every
+/// unnamed level in a PK3/PK7) keep an empty name.
+#[tauri::command]
+async fn extract_wad_level_info(wad_path: String) -> Result<Vec<wad_parser::LevelInfo>, String> {
+    wad_parser::extract_level_info(&wad_path)
+}
+
+/// Extract level names (see `extract_wad_level_names`, including its
+/// fallback to the level ID for unnamed levels) and save them to a JSON
+/// file alongside the WAD. Creates a file named "{wad_filename}.levels.json"
+/// in the same directory.
 #[tauri::command]
 async fn extract_and_save_level_names(wad_path: String) -> Result<String, String> {
     let names = wad_parser::extract_level_names(&wad_path)?;
@@ -42,24 +56,70 @@ async fn extract_and_save_level_names(wad_path: String) -> Result<String, String
     Ok(json_path.to_string_lossy().to_string())
 }
 
-/// Launch GZDoom with the specified executable path and arguments.
-/// This bypasses shell plugin limitations for custom GZDoom paths.
+/// Report which WAD/PK3 files a running GZDoom instance currently has
+/// loaded, so the UI can show "now playing" and reconstruct the active load
+/// order. Degrades to an empty file list (not an error) on platforms where
+/// this introspection isn't available.
+#[tauri::command]
+async fn get_loaded_wads(pid: u32) -> Result<process::LoadedWads, String> {
+    Ok(process::loaded_wads(pid))
+}
+
+/// Walk a folder of WADs/PK3s/PK7s and extract each file's level names,
+/// parsing across `concurrency` worker threads and caching results in
+/// `{file}.levels.cache.json` sidecars so unchanged files are skipped on
+/// repeat scans. Per-file parse errors are returned alongside successful
+/// results rather than failing the whole scan.
+#[tauri::command]
+async fn scan_wad_directory(
+    dir_path: String,
+    concurrency: usize,
+) -> Result<HashMap<String, wad_parser::FileScanResult>, String> {
+    wad_parser::scan_wad_directory(&dir_path, concurrency)
+}
+
+/// Launch GZDoom with the specified arguments.
+///
+/// `gzdoom_path` is either `None` (or empty) to launch the engine bundled
+/// with the app as a Tauri sidecar, or `Some(path)` to launch a validated
+/// system-installed GZDoom at that path. This bypasses shell plugin
+/// limitations for custom GZDoom paths.
 #[tauri::command]
 async fn launch_gzdoom(
-    gzdoom_path: String,
+    app: AppHandle,
+    gzdoom_path: Option<String>,
     args: Vec<String>,
 ) -> Result<(), String> {
-    // Security: Validate the path looks like gzdoom
-    let path_lower = gzdoom_path.to_lowercase();
-    if !path_lower.contains("gzdoom") {
-        return Err("Invalid GZDoom path: must contain 'gzdoom'".to_string());
-    }
+    match gzdoom_path.filter(|p| !p.is_empty()) {
+        Some(path) => {
+            // Security: Validate the path looks like gzdoom
+            let path_lower = path.to_lowercase();
+            if !path_lower.contains("gzdoom") {
+                return Err("Invalid GZDoom path: must contain 'gzdoom'".to_string());
+            }
+
+            // Spawn GZDoom as a detached process
+            Command::new(&path)
+                .args(&args)
+                .spawn()
+                .map_err(|e| format!("Failed to launch GZDoom at '{}': {}", path, e))?;
+        }
+        None => {
+            // No system path configured: launch the engine bundled as a
+            // sidecar binary, resolved the same way Tauri's own
+            // copy_binaries resolves external binaries (target-triple
+            // suffix stripped).
+            let sidecar = app
+                .shell()
+                .sidecar("gzdoom")
+                .map_err(|e| format!("Failed to resolve bundled GZDoom sidecar: {}", e))?;
 
-    // Spawn GZDoom as a detached process
-    Command::new(&gzdoom_path)
-        .args(&args)
-        .spawn()
-        .map_err(|e| format!("Failed to launch GZDoom at '{}': {}", gzdoom_path, e))?;
+            sidecar
+                .args(&args)
+                .spawn()
+                .map_err(|e| format!("Failed to launch bundled GZDoom: {}", e))?;
+        }
+    }
 
     Ok(())
 }
@@ -76,7 +136,10 @@ pub fn run() {
             launch_gzdoom,
             is_process_running,
             extract_wad_level_names,
-            extract_and_save_level_names
+            extract_wad_level_info,
+            extract_and_save_level_names,
+            scan_wad_directory,
+            get_loaded_wads
         ]);
 
     // Enable MCP plugin for AI debugging in development builds
@@ -0,0 +1,192 @@
+//! Cross-platform process introspection for the "is GZDoom running?"
+//! indicator, plus (on platforms that support it) reporting which WAD/PK3
+//! files a running instance has loaded.
+
+use serde::Serialize;
+use std::collections::HashSet;
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+use std::process::Command;
+
+/// File extensions we recognize as WAD-like content a running GZDoom
+/// instance might have loaded.
+const WAD_LIKE_EXTENSIONS: [&str; 4] = ["wad", "pk3", "pk7", "iwad"];
+
+/// Which WAD/PK3 files a running GZDoom instance currently has open.
+#[derive(Serialize)]
+pub struct LoadedWads {
+    pub pid: u32,
+    pub executable: Option<String>,
+    pub loaded_files: Vec<String>,
+}
+
+/// Report the WAD/PK3 files `pid` currently has open, by inspecting
+/// `/proc/<pid>/maps` and `/proc/<pid>/fd` on Linux. On platforms where this
+/// introspection isn't available, this degrades to an empty file list
+/// rather than an error.
+pub fn loaded_wads(pid: u32) -> LoadedWads {
+    #[cfg(target_os = "linux")]
+    {
+        loaded_wads_linux(pid)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        LoadedWads {
+            pid,
+            executable: None,
+            loaded_files: Vec::new(),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn loaded_wads_linux(pid: u32) -> LoadedWads {
+    let executable = std::fs::read_link(format!("/proc/{}/exe", pid))
+        .ok()
+        .map(|p| p.to_string_lossy().to_string());
+
+    let mut loaded_files = Vec::new();
+    let mut seen = HashSet::new();
+
+    if let Ok(maps) = std::fs::read_to_string(format!("/proc/{}/maps", pid)) {
+        for line in maps.lines() {
+            if let Some(pathname) = maps_line_pathname(line) {
+                collect_wad_like(pathname, &mut loaded_files, &mut seen);
+            }
+        }
+    }
+
+    if let Ok(fds) = std::fs::read_dir(format!("/proc/{}/fd", pid)) {
+        for fd in fds.flatten() {
+            if let Ok(target) = std::fs::read_link(fd.path()) {
+                collect_wad_like(&target.to_string_lossy(), &mut loaded_files, &mut seen);
+            }
+        }
+    }
+
+    LoadedWads {
+        pid,
+        executable,
+        loaded_files,
+    }
+}
+
+/// Extract the pathname field from a `/proc/<pid>/maps` line:
+/// "start-end perms offset dev inode pathname". The pathname may itself
+/// contain spaces, so (unlike the first five fields) it can't be taken as a
+/// single whitespace-delimited token — it's whatever is left after skipping
+/// the first five fields and the padding that follows the inode field.
+/// Anonymous mappings omit it entirely.
+#[cfg(target_os = "linux")]
+fn maps_line_pathname(line: &str) -> Option<&str> {
+    let mut rest = line;
+    for _ in 0..5 {
+        rest = rest.trim_start();
+        let field_end = rest.find(char::is_whitespace)?;
+        rest = &rest[field_end..];
+    }
+
+    let pathname = rest.trim_start();
+    if pathname.is_empty() {
+        None
+    } else {
+        Some(pathname)
+    }
+}
+
+/// Append `pathname` to `loaded_files` (de-duplicated, order-preserving) if
+/// its extension looks WAD-like.
+#[cfg(target_os = "linux")]
+fn collect_wad_like(pathname: &str, loaded_files: &mut Vec<String>, seen: &mut HashSet<String>) {
+    let is_wad_like = std::path::Path::new(pathname)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| WAD_LIKE_EXTENSIONS.iter().any(|known| known.eq_ignore_ascii_case(ext)))
+        .unwrap_or(false);
+
+    if is_wad_like && seen.insert(pathname.to_string()) {
+        loaded_files.push(pathname.to_string());
+    }
+}
+
+/// Check whether a process named `process_name` is currently running.
+pub fn is_process_running(process_name: &str) -> Result<bool, String> {
+    #[cfg(target_os = "linux")]
+    {
+        is_running_linux(process_name)
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        is_running_pgrep(process_name)
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        is_running_windows(process_name)
+    }
+}
+
+/// Walk `/proc` directly rather than shelling out to `pgrep`, so this works
+/// in minimal containers/distros that don't ship procps.
+#[cfg(target_os = "linux")]
+fn is_running_linux(process_name: &str) -> Result<bool, String> {
+    let entries =
+        std::fs::read_dir("/proc").map_err(|e| format!("Failed to read /proc: {}", e))?;
+
+    for entry in entries {
+        let Ok(entry) = entry else { continue };
+        let is_pid_dir = entry
+            .file_name()
+            .to_str()
+            .map(|s| s.chars().all(|c| c.is_ascii_digit()))
+            .unwrap_or(false);
+        if !is_pid_dir {
+            continue;
+        }
+
+        if let Some(name) = comm_name(&entry.path()) {
+            if name == process_name {
+                return Ok(true);
+            }
+        }
+    }
+
+    Ok(false)
+}
+
+/// Read the command name for a `/proc/<pid>` directory from its `comm` file.
+#[cfg(target_os = "linux")]
+fn comm_name(proc_dir: &std::path::Path) -> Option<String> {
+    std::fs::read_to_string(proc_dir.join("comm"))
+        .ok()
+        .map(|s| s.trim().to_string())
+}
+
+#[cfg(target_os = "macos")]
+fn is_running_pgrep(process_name: &str) -> Result<bool, String> {
+    let output = Command::new("pgrep")
+        .arg("-x")
+        .arg(process_name)
+        .output()
+        .map_err(|e| format!("Failed to run pgrep: {}", e))?;
+
+    Ok(output.status.success())
+}
+
+#[cfg(target_os = "windows")]
+fn is_running_windows(process_name: &str) -> Result<bool, String> {
+    let image_name = if process_name.to_lowercase().ends_with(".exe") {
+        process_name.to_string()
+    } else {
+        format!("{}.exe", process_name)
+    };
+
+    let output = Command::new("tasklist")
+        .args(["/FI", &format!("IMAGENAME eq {}", image_name), "/NH"])
+        .output()
+        .map_err(|e| format!("Failed to run tasklist: {}", e))?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout.to_lowercase().contains(&image_name.to_lowercase()))
+}
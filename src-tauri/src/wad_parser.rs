@@ -4,12 +4,27 @@
 //! - Header: 4 bytes sig ("IWAD"/"PWAD"), 4 bytes numlumps, 4 bytes diroffset
 //! - Lumps: raw data
 //! - Directory: entries of (4 bytes offset, 4 bytes size, 8 bytes name)
+//!
+//! Community content is also frequently shipped as PK3/PK7 archives, which
+//! are plain ZIP containers holding the same MAPINFO-family lumps as loose
+//! files; see `archive` for that path.
 
+use crate::archive;
 use regex::Regex;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
 use std::fs::File;
 use std::io::{Read, Seek, SeekFrom};
 use std::path::Path;
+use std::sync::{mpsc, Arc, LazyLock, Mutex};
+use std::thread;
+use std::time::UNIX_EPOCH;
+
+/// Lump names we know how to mine for level names, in priority order.
+const MAPINFO_LUMP_NAMES: [&str; 4] = ["ZMAPINFO", "UMAPINFO", "MAPINFO", "DEHACKED"];
+
+/// File extensions `scan_wad_directory` considers part of a WAD library.
+const SCANNABLE_EXTENSIONS: [&str; 3] = ["wad", "pk3", "pk7"];
 
 /// A lump entry from the WAD directory
 struct LumpEntry {
@@ -18,8 +33,35 @@ struct LumpEntry {
     name: String,
 }
 
+/// Structured metadata for one level, gathered from MAPINFO/ZMAPINFO/UMAPINFO
+/// (or, absent those, the WAD's own lump directory). When no human-readable
+/// name is defined, `name` is the level ID itself if the WAD's lump directory
+/// shows the level as a map, and empty otherwise (always so for PK3/PK7
+/// archives, which have no lump directory).
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct LevelInfo {
+    pub id: String,
+    pub name: String,
+    pub music: Option<String>,
+    pub next: Option<String>,
+    pub secret_next: Option<String>,
+    pub par_time: Option<u32>,
+    pub cluster: Option<u32>,
+    pub episode: Option<u32>,
+}
+
 /// Parse a WAD file and extract level names from MAPINFO/ZMAPINFO/UMAPINFO/DEHACKED
 pub fn extract_level_names<P: AsRef<Path>>(wad_path: P) -> Result<HashMap<String, String>, String> {
+    let levels = extract_level_info(wad_path)?;
+    Ok(levels.into_iter().map(|l| (l.id, l.name)).collect())
+}
+
+/// Parse a WAD/PK3/PK7 file and extract richer per-level metadata (name,
+/// music, next map, par time, cluster/episode, ...) from its
+/// MAPINFO/ZMAPINFO/UMAPINFO/DEHACKED lumps, falling back to the lump
+/// directory itself for levels with no defined name. Levels are returned in
+/// the order they were first discovered.
+pub fn extract_level_info<P: AsRef<Path>>(wad_path: P) -> Result<Vec<LevelInfo>, String> {
     let path = wad_path.as_ref();
     let mut file = File::open(path).map_err(|e| format!("Failed to open WAD: {}", e))?;
 
@@ -30,6 +72,9 @@ pub fn extract_level_names<P: AsRef<Path>>(wad_path: P) -> Result<HashMap<String
 
     let sig = String::from_utf8_lossy(&header[0..4]);
     if sig != "IWAD" && sig != "PWAD" {
+        if archive::is_zip(&header) {
+            return extract_level_info_from_archive(&mut file);
+        }
         return Err(format!("Invalid WAD signature: {}", sig));
     }
 
@@ -56,33 +101,139 @@ pub fn extract_level_names<P: AsRef<Path>>(wad_path: P) -> Result<HashMap<String
     }
 
     // Look for MAPINFO lumps (in priority: ZMAPINFO, UMAPINFO, MAPINFO)
-    let mut level_names: HashMap<String, String> = HashMap::new();
+    let mut levels: Vec<LevelInfo> = Vec::new();
 
     // Try ZMAPINFO first (GZDoom extended format)
     if let Some(lump) = lumps.iter().find(|l| l.name == "ZMAPINFO") {
         let content = read_lump_content(&mut file, lump)?;
-        parse_mapinfo(&content, &mut level_names);
+        parse_mapinfo(&content, &mut levels);
     }
 
     // Then UMAPINFO (universal format)
     if let Some(lump) = lumps.iter().find(|l| l.name == "UMAPINFO") {
         let content = read_lump_content(&mut file, lump)?;
-        parse_umapinfo(&content, &mut level_names);
+        parse_umapinfo(&content, &mut levels);
     }
 
     // Then regular MAPINFO
     if let Some(lump) = lumps.iter().find(|l| l.name == "MAPINFO") {
         let content = read_lump_content(&mut file, lump)?;
-        parse_mapinfo(&content, &mut level_names);
+        parse_mapinfo(&content, &mut levels);
     }
 
     // Finally DEHACKED for classic WADs
     if let Some(lump) = lumps.iter().find(|l| l.name == "DEHACKED") {
         let content = read_lump_content(&mut file, lump)?;
-        parse_dehacked(&content, &mut level_names);
+        parse_dehacked(&content, &mut levels);
+    }
+
+    // A plain megawad may define no names at all, so fall back to scanning
+    // the lump directory itself for map markers. This guarantees every
+    // playable level shows up in a "warp to level" list even when the
+    // author never bothered with MAPINFO/DEHACKED.
+    for map_id in find_map_markers(&lumps) {
+        let level = get_or_create_level(&mut levels, &map_id);
+        if level.name.is_empty() {
+            level.name = map_id;
+        }
+    }
+
+    fill_episode_from_id(&mut levels);
+
+    Ok(levels)
+}
+
+/// Find (or append) the `LevelInfo` for `id`, preserving first-discovery
+/// order the same way the old `HashMap::entry().or_insert()` preserved
+/// first-write-wins semantics across the MAPINFO/UMAPINFO/DEHACKED passes.
+fn get_or_create_level<'a>(levels: &'a mut Vec<LevelInfo>, id: &str) -> &'a mut LevelInfo {
+    if let Some(pos) = levels.iter().position(|l| l.id == id) {
+        &mut levels[pos]
+    } else {
+        levels.push(LevelInfo {
+            id: id.to_string(),
+            ..Default::default()
+        });
+        levels.last_mut().unwrap()
+    }
+}
+
+/// Derive `episode` from a classic `ExMy` level ID. MAPINFO/UMAPINFO blocks
+/// are not read for an episode, so `MAPxx` levels keep `None`.
+fn fill_episode_from_id(levels: &mut [LevelInfo]) {
+    let episode_re = Regex::new(r"(?i)^E(\d+)M\d+$").unwrap();
+    for level in levels {
+        if level.episode.is_none() {
+            if let Some(caps) = episode_re.captures(&level.id) {
+                level.episode = caps.get(1).and_then(|m| m.as_str().parse().ok());
+            }
+        }
     }
+}
+
+/// Geometry lumps that follow a vanilla map marker (MAPxx/ExMy).
+const VANILLA_MAP_LUMPS: [&str; 10] = [
+    "THINGS",
+    "LINEDEFS",
+    "SIDEDEFS",
+    "VERTEXES",
+    "SEGS",
+    "SSECTORS",
+    "NODES",
+    "SECTORS",
+    "REJECT",
+    "BLOCKMAP",
+];
+
+/// Find level IDs by scanning the lump directory for a `MAPxx`/`ExMy` marker
+/// immediately followed by the lumps that make up a map (vanilla geometry
+/// lumps, or `TEXTMAP` for a UDMF map ending in `ENDMAP`). This finds levels
+/// that have no MAPINFO/DEHACKED name at all.
+fn find_map_markers(lumps: &[LumpEntry]) -> Vec<String> {
+    let map_marker = Regex::new(r"(?i)^(MAP\d\d|E\dM\d)$").unwrap();
+    let mut map_ids = Vec::new();
+
+    for (i, lump) in lumps.iter().enumerate() {
+        if !map_marker.is_match(&lump.name) {
+            continue;
+        }
+
+        let Some(next) = lumps.get(i + 1) else {
+            continue;
+        };
 
-    Ok(level_names)
+        let is_map = next.name == "TEXTMAP" || VANILLA_MAP_LUMPS.contains(&next.name.as_str());
+        if is_map {
+            map_ids.push(lump.name.to_uppercase());
+        }
+    }
+
+    map_ids
+}
+
+/// Extract level info from a PK3/PK7 (ZIP) archive by pulling the
+/// MAPINFO-family entries out of the central directory and feeding them to
+/// the same parsers used for loose WAD lumps.
+fn extract_level_info_from_archive(file: &mut File) -> Result<Vec<LevelInfo>, String> {
+    let contents = archive::read_named_entries(file, &MAPINFO_LUMP_NAMES)?;
+    let mut levels: Vec<LevelInfo> = Vec::new();
+
+    if let Some(content) = contents.get("ZMAPINFO") {
+        parse_mapinfo(content, &mut levels);
+    }
+    if let Some(content) = contents.get("UMAPINFO") {
+        parse_umapinfo(content, &mut levels);
+    }
+    if let Some(content) = contents.get("MAPINFO") {
+        parse_mapinfo(content, &mut levels);
+    }
+    if let Some(content) = contents.get("DEHACKED") {
+        parse_dehacked(content, &mut levels);
+    }
+
+    fill_episode_from_id(&mut levels);
+
+    Ok(levels)
 }
 
 fn read_lump_content(file: &mut File, lump: &LumpEntry) -> Result<String, String> {
@@ -97,8 +248,9 @@ fn read_lump_content(file: &mut File, lump: &LumpEntry) -> Result<String, String
 }
 
 /// Parse MAPINFO/ZMAPINFO format
-/// Looks for: map MAP01 "Level Name" or map MAP01 lookup "HUSTR_1"
-fn parse_mapinfo(content: &str, names: &mut HashMap<String, String>) {
+/// Looks for: map MAP01 "Level Name" or map MAP01 lookup "HUSTR_1", plus the
+/// block form `map MAP01 { levelname = "..." music = "..." ... }`.
+fn parse_mapinfo(content: &str, levels: &mut Vec<LevelInfo>) {
     // Pattern: map MAP01 "Level Name" or map E1M1 "Level Name"
     // Also handles: map MAP01 lookup "HUSTR_E1M1"
     let re = Regex::new(r#"(?i)^\s*map\s+(MAP\d+|E\d+M\d+)\s+"([^"]+)""#).unwrap();
@@ -110,50 +262,104 @@ fn parse_mapinfo(content: &str, names: &mut HashMap<String, String>) {
 
             // Skip if it's a lookup reference
             if !map_name.starts_with("HUSTR") && !map_name.starts_with("$") {
-                names.entry(map_id).or_insert(map_name);
+                let level = get_or_create_level(levels, &map_id);
+                if level.name.is_empty() {
+                    level.name = map_name;
+                }
             }
         }
     }
 
-    // Also try block format: map MAP01 { levelname = "Level Name" }
+    // Also try block format. The dominant GZDoom form carries the inline
+    // name between the id and the brace (`map MAP01 "Entryway" { ... }`),
+    // so the optional quoted name has to be skipped rather than required
+    // to butt up against the id.
     let block_re =
-        Regex::new(r#"(?i)map\s+(MAP\d+|E\d+M\d+)\s*\{([^}]*)\}"#).unwrap();
-    let name_re = Regex::new(r#"(?i)levelname\s*=\s*"([^"]+)""#).unwrap();
-
+        Regex::new(r#"(?i)map\s+(MAP\d+|E\d+M\d+)\s*(?:"[^"]*"\s*)?\{([^}]*)\}"#).unwrap();
     for caps in block_re.captures_iter(content) {
         let map_id = caps.get(1).unwrap().as_str().to_uppercase();
         let block = caps.get(2).unwrap().as_str();
-
-        if let Some(name_caps) = name_re.captures(block) {
-            let map_name = name_caps.get(1).unwrap().as_str().to_string();
-            if !map_name.starts_with("$") {
-                names.entry(map_id).or_insert(map_name);
-            }
-        }
+        apply_mapinfo_block(levels, &map_id, block);
     }
 }
 
-/// Parse UMAPINFO format (slightly different syntax)
-fn parse_umapinfo(content: &str, names: &mut HashMap<String, String>) {
-    // UMAPINFO uses: MAP MAP01 { levelname = "Level Name" }
+/// Parse UMAPINFO format (slightly different syntax, but the same
+/// `{ key = value }` block body).
+fn parse_umapinfo(content: &str, levels: &mut Vec<LevelInfo>) {
+    // UMAPINFO uses: MAP MAP01 { levelname = "Level Name" ... }
     let block_re =
-        Regex::new(r#"(?i)MAP\s+(MAP\d+|E\d+M\d+)\s*\{([^}]*)\}"#).unwrap();
-    let name_re = Regex::new(r#"(?i)levelname\s*=\s*"([^"]+)""#).unwrap();
-
+        Regex::new(r#"(?i)MAP\s+(MAP\d+|E\d+M\d+)\s*(?:"[^"]*"\s*)?\{([^}]*)\}"#).unwrap();
     for caps in block_re.captures_iter(content) {
         let map_id = caps.get(1).unwrap().as_str().to_uppercase();
         let block = caps.get(2).unwrap().as_str();
+        apply_mapinfo_block(levels, &map_id, block);
+    }
+}
 
-        if let Some(name_caps) = name_re.captures(block) {
-            let map_name = name_caps.get(1).unwrap().as_str().to_string();
-            names.entry(map_id).or_insert(map_name);
+static MAPINFO_NAME_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)\blevelname\s*=\s*"([^"]+)""#).unwrap());
+static MAPINFO_MUSIC_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)\bmusic\s*=\s*"([^"]+)""#).unwrap());
+static MAPINFO_NEXT_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)\bnext\s*=\s*"([^"]+)""#).unwrap());
+static MAPINFO_SECRET_NEXT_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)\b(?:secretnext|nextsecret)\s*=\s*"([^"]+)""#).unwrap());
+static MAPINFO_PAR_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)\b(?:par|partime)\s*=\s*(\d+)"#).unwrap());
+static MAPINFO_CLUSTER_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)\bcluster\s*=\s*(\d+)"#).unwrap());
+
+/// Pull the fields we track (name, music, next, secret next, par time,
+/// cluster) out of a MAPINFO/UMAPINFO `{ ... }` block and apply them to
+/// `map_id`'s `LevelInfo`, without overwriting fields an earlier (higher
+/// priority) lump already set.
+fn apply_mapinfo_block(levels: &mut Vec<LevelInfo>, map_id: &str, block: &str) {
+    let name_re = &*MAPINFO_NAME_RE;
+    let music_re = &*MAPINFO_MUSIC_RE;
+    let next_re = &*MAPINFO_NEXT_RE;
+    let secret_next_re = &*MAPINFO_SECRET_NEXT_RE;
+    let par_re = &*MAPINFO_PAR_RE;
+    let cluster_re = &*MAPINFO_CLUSTER_RE;
+
+    let level = get_or_create_level(levels, map_id);
+
+    if let Some(caps) = name_re.captures(block) {
+        let map_name = caps.get(1).unwrap().as_str().to_string();
+        if level.name.is_empty() && !map_name.starts_with('$') {
+            level.name = map_name;
         }
     }
+    if level.music.is_none() {
+        level.music = music_re
+            .captures(block)
+            .map(|c| c.get(1).unwrap().as_str().to_string());
+    }
+    if level.next.is_none() {
+        level.next = next_re
+            .captures(block)
+            .map(|c| c.get(1).unwrap().as_str().to_string());
+    }
+    if level.secret_next.is_none() {
+        level.secret_next = secret_next_re
+            .captures(block)
+            .map(|c| c.get(1).unwrap().as_str().to_string());
+    }
+    if level.par_time.is_none() {
+        level.par_time = par_re
+            .captures(block)
+            .and_then(|c| c.get(1).unwrap().as_str().parse().ok());
+    }
+    if level.cluster.is_none() {
+        level.cluster = cluster_re
+            .captures(block)
+            .and_then(|c| c.get(1).unwrap().as_str().parse().ok());
+    }
 }
 
 /// Parse DEHACKED format for level names
-/// Looks for [STRINGS] section with HUSTR_1, HUSTR_E1M1, etc.
-fn parse_dehacked(content: &str, names: &mut HashMap<String, String>) {
+/// Looks for [STRINGS] section with HUSTR_1, HUSTR_E1M1, etc. DEHACKED
+/// carries no music/par/next data, so this only ever sets `name`.
+fn parse_dehacked(content: &str, levels: &mut Vec<LevelInfo>) {
     let mut in_strings = false;
 
     // HUSTR_1 through HUSTR_32 map to MAP01-MAP32
@@ -184,7 +390,10 @@ fn parse_dehacked(content: &str, names: &mut HashMap<String, String>) {
             if num >= 1 && num <= 32 {
                 let map_id = format!("MAP{:02}", num);
                 let map_name = caps.get(2).unwrap().as_str().trim().to_string();
-                names.entry(map_id).or_insert(map_name);
+                let level = get_or_create_level(levels, &map_id);
+                if level.name.is_empty() {
+                    level.name = map_name;
+                }
             }
         }
 
@@ -192,15 +401,153 @@ fn parse_dehacked(content: &str, names: &mut HashMap<String, String>) {
         if let Some(caps) = doom1_re.captures(trimmed) {
             let map_id = caps.get(1).unwrap().as_str().to_uppercase();
             let map_name = caps.get(2).unwrap().as_str().trim().to_string();
-            names.entry(map_id).or_insert(map_name);
+            let level = get_or_create_level(levels, &map_id);
+            if level.name.is_empty() {
+                level.name = map_name;
+            }
+        }
+    }
+}
+
+/// Level names discovered for one file, or the error hit while parsing it.
+/// Kept separate per file so one bad archive in a library doesn't abort the
+/// whole scan.
+#[derive(Serialize)]
+pub struct FileScanResult {
+    pub levels: HashMap<String, String>,
+    pub error: Option<String>,
+}
+
+/// The `{wad}.levels.cache.json` sidecar format: the source file's
+/// size/mtime at cache time plus the level names extracted from it, so a
+/// later scan can tell whether the file has changed since. Kept separate
+/// from the plain `{wad}.levels.json` written by `extract_and_save_level_names`
+/// so the two don't clobber or misread each other's shape.
+#[derive(Serialize, Deserialize)]
+struct LevelsCache {
+    size: u64,
+    mtime: u64,
+    levels: HashMap<String, String>,
+}
+
+/// Walk `dir` for WAD/PK3/PK7 files and extract each one's level names,
+/// parsing across `concurrency` worker threads since this is IO- and
+/// CPU-bound. Results are cached in a `{file}.levels.cache.json` sidecar
+/// keyed on file size and mtime, so unchanged files are skipped on repeated
+/// scans.
+pub fn scan_wad_directory<P: AsRef<Path>>(
+    dir: P,
+    concurrency: usize,
+) -> Result<HashMap<String, FileScanResult>, String> {
+    let dir = dir.as_ref();
+    let read_dir = std::fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read directory '{}': {}", dir.display(), e))?;
+
+    let mut files = VecDeque::new();
+    for entry in read_dir {
+        let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
+        let path = entry.path();
+        let is_scannable = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| SCANNABLE_EXTENSIONS.iter().any(|known| known.eq_ignore_ascii_case(ext)))
+            .unwrap_or(false);
+        if is_scannable {
+            files.push_back(path);
+        }
+    }
+
+    let work = Arc::new(Mutex::new(files));
+    let (tx, rx) = mpsc::channel();
+    let worker_count = concurrency.max(1);
+
+    let mut handles = Vec::with_capacity(worker_count);
+    for _ in 0..worker_count {
+        let work = Arc::clone(&work);
+        let tx = tx.clone();
+        handles.push(thread::spawn(move || loop {
+            let path = match work.lock().unwrap().pop_front() {
+                Some(path) => path,
+                None => break,
+            };
+
+            let key = path.to_string_lossy().to_string();
+            let result = scan_one_file(&path);
+            if tx.send((key, result)).is_err() {
+                break;
+            }
+        }));
+    }
+    drop(tx);
+
+    let mut results = HashMap::new();
+    for (key, result) in rx {
+        results.insert(key, result);
+    }
+
+    for handle in handles {
+        let _ = handle.join();
+    }
+
+    Ok(results)
+}
+
+/// Parse one file, consulting (and refreshing) its `.levels.cache.json` sidecar.
+fn scan_one_file(path: &Path) -> FileScanResult {
+    match scan_one_file_inner(path) {
+        Ok(levels) => FileScanResult { levels, error: None },
+        Err(e) => FileScanResult {
+            levels: HashMap::new(),
+            error: Some(e),
+        },
+    }
+}
+
+fn scan_one_file_inner(path: &Path) -> Result<HashMap<String, String>, String> {
+    let metadata = std::fs::metadata(path)
+        .map_err(|e| format!("Failed to stat '{}': {}", path.display(), e))?;
+    let size = metadata.len();
+    let mtime = metadata
+        .modified()
+        .map_err(|e| format!("Failed to read mtime for '{}': {}", path.display(), e))?
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| format!("Invalid mtime for '{}': {}", path.display(), e))?
+        .as_secs();
+
+    let cache_path = path.with_extension("levels.cache.json");
+    if let Some(cached) = read_levels_cache(&cache_path) {
+        if cached.size == size && cached.mtime == mtime {
+            return Ok(cached.levels);
         }
     }
+
+    let levels = extract_level_names(path)?;
+
+    let cache = LevelsCache {
+        size,
+        mtime,
+        levels: levels.clone(),
+    };
+    if let Ok(json) = serde_json::to_string_pretty(&cache) {
+        let _ = std::fs::write(&cache_path, json);
+    }
+
+    Ok(levels)
+}
+
+fn read_levels_cache(cache_path: &Path) -> Option<LevelsCache> {
+    let content = std::fs::read_to_string(cache_path).ok()?;
+    serde_json::from_str(&content).ok()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn find<'a>(levels: &'a [LevelInfo], id: &str) -> &'a LevelInfo {
+        levels.iter().find(|l| l.id == id).unwrap()
+    }
+
     #[test]
     fn test_parse_mapinfo_simple() {
         let content = r#"
@@ -208,12 +555,12 @@ map MAP01 "Entryway"
 map MAP02 "Underhalls"
 map E1M1 "Hangar"
 "#;
-        let mut names = HashMap::new();
-        parse_mapinfo(content, &mut names);
+        let mut levels = Vec::new();
+        parse_mapinfo(content, &mut levels);
 
-        assert_eq!(names.get("MAP01"), Some(&"Entryway".to_string()));
-        assert_eq!(names.get("MAP02"), Some(&"Underhalls".to_string()));
-        assert_eq!(names.get("E1M1"), Some(&"Hangar".to_string()));
+        assert_eq!(find(&levels, "MAP01").name, "Entryway");
+        assert_eq!(find(&levels, "MAP02").name, "Underhalls");
+        assert_eq!(find(&levels, "E1M1").name, "Hangar");
     }
 
     #[test]
@@ -222,11 +569,17 @@ map E1M1 "Hangar"
 map MAP01 {
     levelname = "Test Level"
     music = "D_RUNNIN"
+    next = "MAP02"
+    par = 90
 }
 "#;
-        let mut names = HashMap::new();
-        parse_mapinfo(content, &mut names);
-
-        assert_eq!(names.get("MAP01"), Some(&"Test Level".to_string()));
+        let mut levels = Vec::new();
+        parse_mapinfo(content, &mut levels);
+
+        let map01 = find(&levels, "MAP01");
+        assert_eq!(map01.name, "Test Level");
+        assert_eq!(map01.music.as_deref(), Some("D_RUNNIN"));
+        assert_eq!(map01.next.as_deref(), Some("MAP02"));
+        assert_eq!(map01.par_time, Some(90));
     }
 }